@@ -0,0 +1,108 @@
+//! Sidecar launch configuration: extra environment variables, an explicit
+//! working directory, and how long to wait for the readiness probe.
+//!
+//! Mirrors what Erlang ports expose via `env`/`cd` open options instead of
+//! just inheriting whatever environment the app happens to have, and fails
+//! fast (like the Erlang `bad_env` check) on malformed entries rather than
+//! silently dropping them.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Default total time budget for the readiness probe before giving up.
+pub const DEFAULT_READY_TOTAL_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default per-request timeout while probing readiness.
+pub const DEFAULT_READY_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Launch-time configuration for the sidecar process.
+#[derive(Debug, Clone)]
+pub struct SidecarConfig {
+    /// Extra `KEY=VALUE` environment variables to add on top of the app's own.
+    pub env: Vec<(String, String)>,
+    /// Working directory to launch the sidecar in, if not the app's own.
+    pub working_dir: Option<PathBuf>,
+    /// Total deadline for the readiness probe to succeed.
+    pub ready_total_timeout: Duration,
+    /// Timeout for each individual readiness request.
+    pub ready_attempt_timeout: Duration,
+}
+
+impl Default for SidecarConfig {
+    fn default() -> Self {
+        Self {
+            env: Vec::new(),
+            working_dir: None,
+            ready_total_timeout: DEFAULT_READY_TOTAL_TIMEOUT,
+            ready_attempt_timeout: DEFAULT_READY_ATTEMPT_TIMEOUT,
+        }
+    }
+}
+
+impl SidecarConfig {
+    /// Build a config from the app's own environment variables:
+    /// `SIDECAR_EXTRA_ENV` (comma-separated `KEY=VALUE` pairs),
+    /// `SIDECAR_WORKING_DIR`, and `SIDECAR_READY_TIMEOUT_SECS`.
+    pub fn from_env() -> Result<Self, String> {
+        let mut config = Self::default();
+
+        if let Some(raw_os) = std::env::var_os("SIDECAR_EXTRA_ENV") {
+            let raw = raw_os
+                .into_string()
+                .map_err(|_| "SIDECAR_EXTRA_ENV is not valid UTF-8".to_string())?;
+            for entry in raw.split(',') {
+                let entry = entry.trim();
+                if entry.is_empty() {
+                    continue;
+                }
+                let (key, value) = entry.split_once('=').ok_or_else(|| {
+                    format!("malformed SIDECAR_EXTRA_ENV entry (expected KEY=VALUE): {entry}")
+                })?;
+                validate_env_entry(key, value)?;
+                config.env.push((key.to_string(), value.to_string()));
+            }
+        }
+
+        if let Some(raw_dir) = std::env::var_os("SIDECAR_WORKING_DIR") {
+            let dir = raw_dir
+                .into_string()
+                .map_err(|_| "SIDECAR_WORKING_DIR is not valid UTF-8".to_string())?;
+            if dir.contains('\0') {
+                return Err("SIDECAR_WORKING_DIR contains an embedded NUL".to_string());
+            }
+            config.working_dir = Some(PathBuf::from(dir));
+        }
+
+        if let Ok(raw) = std::env::var("SIDECAR_READY_TIMEOUT_SECS") {
+            let secs: u64 = raw
+                .parse()
+                .map_err(|_| format!("invalid SIDECAR_READY_TIMEOUT_SECS: {raw:?}"))?;
+            config.ready_total_timeout = Duration::from_secs(secs);
+        }
+
+        Ok(config)
+    }
+
+    /// Apply the working directory and extra env vars to a `std::process::Command`.
+    pub fn apply_to(&self, cmd: &mut std::process::Command) {
+        if let Some(dir) = &self.working_dir {
+            cmd.current_dir(dir);
+        }
+        for (key, value) in &self.env {
+            cmd.env(key, value);
+        }
+    }
+}
+
+/// Reject embedded NULs (which would truncate the C string the OS sees) and
+/// empty names, the same class of error Erlang's `open_port` rejects as `bad_env`.
+fn validate_env_entry(key: &str, value: &str) -> Result<(), String> {
+    if key.is_empty() {
+        return Err("environment variable name must not be empty".to_string());
+    }
+    if key.contains('\0') || value.contains('\0') {
+        return Err(format!(
+            "environment variable {key:?} contains an embedded NUL"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,244 @@
+//! Length-prefixed packet framing for the sidecar's stdout channel.
+//!
+//! Modeled on Erlang port `{packet, N}` framing: each message is prefixed
+//! with an N-byte big-endian length header (`N` is 2 or 4 bytes), and the
+//! payload that follows is JSON decoded into a [`SidecarMessage`]. This
+//! replaces brittle substring matching on raw log lines with typed
+//! lifecycle signals (readiness, progress, structured logs, fatal errors).
+
+use serde::Deserialize;
+
+/// Reject any declared payload length above this by default, so a corrupted
+/// or malicious header can't make us buffer an unbounded amount of data.
+pub const DEFAULT_MAX_PAYLOAD_LEN: usize = 8 * 1024 * 1024;
+
+/// Width of the big-endian length header prefixing each packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderWidth {
+    Two,
+    Four,
+}
+
+impl HeaderWidth {
+    fn byte_len(self) -> usize {
+        match self {
+            HeaderWidth::Two => 2,
+            HeaderWidth::Four => 4,
+        }
+    }
+
+    fn decode(self, header: &[u8]) -> usize {
+        match self {
+            HeaderWidth::Two => u16::from_be_bytes([header[0], header[1]]) as usize,
+            HeaderWidth::Four => {
+                u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize
+            }
+        }
+    }
+}
+
+/// A typed lifecycle message decoded from the sidecar's framed stdout channel.
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SidecarMessage {
+    Ready,
+    Progress { pct: u8 },
+    Log { level: String, msg: String },
+    Error { code: String },
+}
+
+/// One decoded unit of output from [`PacketFramer::feed`]: either a proper
+/// framed lifecycle message, or a raw text line recovered while resyncing
+/// after stray unframed bytes (a PyInstaller bootloader line, a traceback,
+/// ...) leaked onto the same stdout stream as the framed channel.
+#[derive(Debug, Clone)]
+pub enum FrameEvent {
+    Message(SidecarMessage),
+    RawLine(String),
+    /// A complete frame whose payload didn't decode as a `SidecarMessage`.
+    /// Surfaced as an event (not a `feed` error) so earlier well-formed
+    /// frames already decoded in this same call aren't thrown away with it.
+    MalformedPacket(String),
+}
+
+/// Stateful decoder that accumulates raw bytes across multiple `Stdout`
+/// chunks and yields fully-decoded [`SidecarMessage`]s as soon as they're
+/// complete.
+pub struct PacketFramer {
+    header_width: HeaderWidth,
+    max_payload_len: usize,
+    buffer: Vec<u8>,
+}
+
+impl PacketFramer {
+    pub fn new(header_width: HeaderWidth) -> Self {
+        Self::with_max_payload_len(header_width, DEFAULT_MAX_PAYLOAD_LEN)
+    }
+
+    pub fn with_max_payload_len(header_width: HeaderWidth, max_payload_len: usize) -> Self {
+        Self {
+            header_width,
+            max_payload_len,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Feed a raw chunk as delivered by the sidecar's stdout stream. Returns
+    /// every packet, recovered text line, or malformed-packet notice that
+    /// became complete as a result, in arrival order.
+    ///
+    /// A declared length over the cap almost always means `buffer[..header_len]`
+    /// isn't a real header at all - plain text (a PyInstaller bootloader line,
+    /// a traceback) leaked onto the same stdout stream as the framed channel.
+    /// Rather than drop exactly `header_len` bytes and immediately reinterpret
+    /// the next few bytes of that same text as another header, we resync on
+    /// the next newline: everything up to it is surfaced as a
+    /// [`FrameEvent::RawLine`] and framing resumes right after it.
+    ///
+    /// A JSON decode error (declared length was plausible, payload wasn't
+    /// valid) is surfaced as [`FrameEvent::MalformedPacket`] rather than
+    /// failing the whole call, so any good frames already decoded earlier in
+    /// this same chunk are still returned instead of discarded; the buffer is
+    /// left past the bad packet either way, so the caller can keep feeding
+    /// subsequent chunks instead of wedging forever.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<FrameEvent>, String> {
+        self.buffer.extend_from_slice(chunk);
+        let header_len = self.header_width.byte_len();
+        let mut events = Vec::new();
+
+        loop {
+            if self.buffer.len() < header_len {
+                break; // wait for the rest of the header
+            }
+
+            let declared_len = self.header_width.decode(&self.buffer[..header_len]);
+            if declared_len > self.max_payload_len {
+                match self.buffer.iter().position(|&b| b == b'\n') {
+                    Some(newline_at) => {
+                        let raw: Vec<u8> = self.buffer.drain(..=newline_at).collect();
+                        let line = String::from_utf8_lossy(&raw).trim_end().to_string();
+                        if !line.is_empty() {
+                            events.push(FrameEvent::RawLine(line));
+                        }
+                        continue;
+                    }
+                    None => {
+                        if self.buffer.len() > self.max_payload_len {
+                            // No newline yet and the garbage is growing
+                            // unbounded; drop it rather than buffer forever
+                            // waiting for a resync point.
+                            self.buffer.clear();
+                        }
+                        break; // wait for more data to find a resync point
+                    }
+                }
+            }
+
+            if self.buffer.len() < header_len + declared_len {
+                break; // wait for the rest of the payload
+            }
+
+            let payload = self.buffer[header_len..header_len + declared_len].to_vec();
+            self.buffer.drain(..header_len + declared_len);
+
+            match serde_json::from_slice::<SidecarMessage>(&payload) {
+                Ok(message) => events.push(FrameEvent::Message(message)),
+                Err(e) => events.push(FrameEvent::MalformedPacket(format!(
+                    "invalid sidecar packet payload: {}",
+                    e
+                ))),
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(payload: &[u8]) -> Vec<u8> {
+        let mut out = (payload.len() as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn decodes_a_single_complete_packet() {
+        let mut framer = PacketFramer::new(HeaderWidth::Four);
+        let bytes = packet(br#"{"type":"ready"}"#);
+        let events = framer.feed(&bytes).unwrap();
+        assert!(matches!(events.as_slice(), [FrameEvent::Message(SidecarMessage::Ready)]));
+    }
+
+    #[test]
+    fn reassembles_a_packet_split_across_chunks() {
+        let mut framer = PacketFramer::new(HeaderWidth::Four);
+        let bytes = packet(br#"{"type":"progress","pct":42}"#);
+        let (first, second) = bytes.split_at(3);
+
+        assert!(framer.feed(first).unwrap().is_empty());
+        let events = framer.feed(second).unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [FrameEvent::Message(SidecarMessage::Progress { pct: 42 })]
+        ));
+    }
+
+    #[test]
+    fn decodes_multiple_packets_in_one_chunk() {
+        let mut framer = PacketFramer::new(HeaderWidth::Two);
+        let mut bytes = Vec::new();
+        for payload in [br#"{"type":"ready"}"#.as_slice(), br#"{"type":"error","code":"E1"}"#] {
+            let mut header = (payload.len() as u16).to_be_bytes().to_vec();
+            header.extend_from_slice(payload);
+            bytes.extend_from_slice(&header);
+        }
+
+        let events = framer.feed(&bytes).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], FrameEvent::Message(SidecarMessage::Ready)));
+        assert!(matches!(
+            &events[1],
+            FrameEvent::Message(SidecarMessage::Error { code }) if code == "E1"
+        ));
+    }
+
+    #[test]
+    fn keeps_earlier_good_frames_when_a_later_one_fails_to_decode() {
+        let mut framer = PacketFramer::new(HeaderWidth::Four);
+        let mut bytes = packet(br#"{"type":"ready"}"#);
+        bytes.extend_from_slice(&packet(b"not json"));
+
+        let events = framer.feed(&bytes).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], FrameEvent::Message(SidecarMessage::Ready)));
+        assert!(matches!(events[1], FrameEvent::MalformedPacket(_)));
+    }
+
+    #[test]
+    fn resyncs_past_an_absurd_declared_length_on_the_next_newline() {
+        let mut framer = PacketFramer::with_max_payload_len(HeaderWidth::Four, 16);
+        let mut bytes = 1000u32.to_be_bytes().to_vec();
+        bytes.extend_from_slice(b"ack garbage\n");
+        bytes.extend_from_slice(&packet(br#"{"type":"ready"}"#));
+
+        let events = framer.feed(&bytes).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], FrameEvent::RawLine(line) if line.ends_with("ack garbage")));
+        assert!(matches!(events[1], FrameEvent::Message(SidecarMessage::Ready)));
+    }
+
+    #[test]
+    fn drops_unbounded_garbage_with_no_newline_instead_of_buffering_forever() {
+        let mut framer = PacketFramer::with_max_payload_len(HeaderWidth::Four, 16);
+        let mut bytes = 1000u32.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&[b'x'; 32]);
+
+        assert!(framer.feed(&bytes).unwrap().is_empty());
+
+        let events = framer.feed(&packet(br#"{"type":"ready"}"#)).unwrap();
+        assert!(matches!(events.as_slice(), [FrameEvent::Message(SidecarMessage::Ready)]));
+    }
+}
@@ -0,0 +1,184 @@
+//! Content-integrity and atomic extraction for the sidecar bundle.
+//!
+//! `setup_sidecar_bundle` used to decide whether to re-extract by comparing
+//! only the byte size of the source and extracted executable, which silently
+//! misses corruption, interrupted copies, and same-size rebuilds. This module
+//! hashes the extracted executable and the `_internal` archive with SHA-256,
+//! records the hashes in a
+//! `manifest.json`, and extracts into a `.partial-<id>` staging directory
+//! that's only `rename`d into place once the manifest has been written -
+//! so a kill mid-extraction can never leave a half-populated bundle that
+//! happens to pass a size check.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Hash + size of a single extracted file, recorded relative to the cache dir.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Maps every extracted file outside the `_internal` archive (currently just
+/// the sidecar executable) to its expected hash, plus the hash of the source
+/// executable and of the `_internal.tar.zst` archive the bundle was
+/// extracted from. `_internal`'s contents are verified against the single
+/// archive hash rather than a per-file entry - hashing tens of thousands of
+/// extracted files on every launch is exactly the cost bundling them as one
+/// archive was meant to avoid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub source_exe_hash: String,
+    pub internal_archive_hash: String,
+    pub files: BTreeMap<String, FileEntry>,
+}
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// SHA-256 of a file's contents, hex-encoded.
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Read and parse `cache_dir/manifest.json`, if present and well-formed.
+pub fn read_manifest(cache_dir: &Path) -> Option<Manifest> {
+    let raw = std::fs::read(cache_dir.join(MANIFEST_FILE_NAME)).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+fn write_manifest(dir: &Path, manifest: &Manifest) -> io::Result<()> {
+    let json = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    std::fs::write(dir.join(MANIFEST_FILE_NAME), json)
+}
+
+/// Whether the cached bundle needs (re-)extraction: true if there's no
+/// manifest yet, or the source executable's or `_internal` archive's hash no
+/// longer matches the ones the manifest was built from.
+pub fn needs_extraction(cache_dir: &Path, source_exe: &Path, internal_archive: &Path) -> bool {
+    let manifest = match read_manifest(cache_dir) {
+        Some(m) => m,
+        None => return true,
+    };
+    match (hash_file(source_exe), hash_file(internal_archive)) {
+        (Ok(exe_hash), Ok(archive_hash)) => {
+            exe_hash != manifest.source_exe_hash || archive_hash != manifest.internal_archive_hash
+        }
+        _ => true,
+    }
+}
+
+/// Check every file the manifest's `files` map knows about (the extracted
+/// executable; `_internal`'s contents are covered by `internal_archive_hash`
+/// instead) against the cache dir, and return the relative paths that are
+/// missing or whose content no longer matches the recorded hash.
+pub fn find_mismatches(cache_dir: &Path, manifest: &Manifest) -> Vec<String> {
+    manifest
+        .files
+        .iter()
+        .filter(|(rel_path, entry)| {
+            let full_path = cache_dir.join(rel_path);
+            match hash_file(&full_path) {
+                Ok(hash) => hash != entry.sha256,
+                Err(_) => true, // missing or unreadable counts as a mismatch
+            }
+        })
+        .map(|(rel_path, _)| rel_path.clone())
+        .collect()
+}
+
+/// Whether the extracted `_internal` directory is missing or empty, even
+/// though the manifest's hashes otherwise matched - e.g. a user deleting it
+/// by hand between launches.
+pub fn internal_dir_missing(cache_dir: &Path) -> bool {
+    match std::fs::read_dir(cache_dir.join("_internal")) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => true,
+    }
+}
+
+/// A unique, monotonically-distinct suffix for staging directory names.
+fn unique_suffix() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{}-{}-{}", std::process::id(), nanos, n)
+}
+
+/// Extract into a fresh `.partial-<id>` directory via `populate`, write the
+/// manifest last, then atomically swap it into `cache_dir`'s place.
+///
+/// `populate` receives the staging directory and must return the manifest
+/// describing everything it wrote. On success, any previous contents of
+/// `cache_dir` are replaced. A process kill before the old `cache_dir` is
+/// renamed aside leaves it exactly as it was; a kill between that rename and
+/// the final one leaves `cache_dir` absent (the old contents remain in a
+/// `.old-<id>` sibling), which `needs_extraction` treats as needing a fresh
+/// extraction.
+pub fn extract_atomic(
+    cache_dir: &Path,
+    populate: impl FnOnce(&Path) -> io::Result<Manifest>,
+) -> io::Result<()> {
+    let parent = cache_dir.parent().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "cache dir has no parent")
+    })?;
+    let dir_name = cache_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("sidecar");
+
+    let staging_dir = parent.join(format!("{dir_name}.partial-{}", unique_suffix()));
+    if staging_dir.exists() {
+        std::fs::remove_dir_all(&staging_dir)?;
+    }
+    std::fs::create_dir_all(&staging_dir)?;
+
+    let manifest = match populate(&staging_dir) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&staging_dir);
+            return Err(e);
+        }
+    };
+
+    // Manifest is the last thing written, so its presence means extraction
+    // completed and every file it references is already on disk.
+    write_manifest(&staging_dir, &manifest)?;
+
+    if cache_dir.exists() {
+        let retiring_dir = parent.join(format!("{dir_name}.old-{}", unique_suffix()));
+        std::fs::rename(cache_dir, &retiring_dir)?;
+        std::fs::rename(&staging_dir, cache_dir)?;
+        let _ = std::fs::remove_dir_all(&retiring_dir);
+    } else {
+        std::fs::rename(&staging_dir, cache_dir)?;
+    }
+
+    Ok(())
+}
+
+/// Record a single file's hash/size into a manifest under construction.
+pub fn record_file(manifest: &mut Manifest, rel_path: PathBuf, full_path: &Path) -> io::Result<()> {
+    let size = std::fs::metadata(full_path)?.len();
+    let sha256 = hash_file(full_path)?;
+    manifest.files.insert(
+        rel_path.to_string_lossy().replace('\\', "/"),
+        FileEntry { sha256, size },
+    );
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Structured logging: a rotating-file `log::Log` implementation that every
+//! `log::<level>!` call site - including the sidecar reader threads - funnels
+//! through, plus an in-memory ring buffer of recent lines so users can attach
+//! logs to bug reports without a terminal.
+//!
+//! Initialized once, as early as possible in `main`, so startup logging
+//! (port selection, leftover-process cleanup) lands in the same log file as
+//! everything that follows, rather than only appearing when `RUST_LOG` is set
+//! and a terminal happens to be attached.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// How many recent log lines to keep in memory for `get_recent_logs`.
+const RING_BUFFER_CAPACITY: usize = 500;
+
+/// Rotate the active log file once it grows past this size.
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+static RECENT_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+struct RotatingFileLogger {
+    level: log::LevelFilter,
+    state: Mutex<LogFileState>,
+}
+
+struct LogFileState {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl log::Log for RotatingFileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} [{}] {}",
+            timestamp(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        eprintln!("{}", line);
+        remember_line(&line);
+
+        if let Ok(mut state) = self.state.lock() {
+            let bytes = line.len() as u64 + 1;
+            if state.written + bytes > MAX_LOG_FILE_BYTES {
+                rotate(&mut state);
+            }
+            if writeln!(state.file, "{}", line).is_ok() {
+                state.written += bytes;
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(state) = self.state.lock() {
+            let _ = state.file.sync_all();
+        }
+    }
+}
+
+/// Move the current log file to `app.log.1` (overwriting any previous one)
+/// and start a fresh file. Size-based rather than day-based since the
+/// sidecar's chattiest failure mode (crash-restart loops) is bounded by byte
+/// volume, not wall-clock time.
+fn rotate(state: &mut LogFileState) {
+    let rotated = state.path.with_extension("log.1");
+    let _ = std::fs::rename(&state.path, &rotated);
+    if let Ok(file) = OpenOptions::new().create(true).append(true).open(&state.path) {
+        state.file = file;
+        state.written = 0;
+    }
+}
+
+fn timestamp() -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("{}.{:03}", now.as_secs(), now.subsec_millis())
+}
+
+fn remember_line(line: &str) {
+    if let Ok(mut buf) = RECENT_LINES.lock() {
+        if buf.len() >= RING_BUFFER_CAPACITY {
+            buf.pop_front();
+        }
+        buf.push_back(line.to_string());
+    }
+}
+
+/// Initialize the global logger, writing to `<dir>/app.log` (rotated to
+/// `app.log.1` past `MAX_LOG_FILE_BYTES`) in addition to stderr. Safe to call
+/// at most once; later calls, or a failure to create the log file, are
+/// swallowed so a logging problem never takes down startup (mirrors
+/// `env_logger::try_init`'s best-effort behavior).
+pub fn init(dir: &Path, level: log::LevelFilter) {
+    if LOG_DIR.get().is_some() {
+        return;
+    }
+
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        eprintln!("logging: failed to create log directory {:?}: {}", dir, e);
+        return;
+    }
+
+    let path = dir.join("app.log");
+    let file = match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("logging: failed to open log file {:?}: {}", path, e);
+            return;
+        }
+    };
+    let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+    let logger = RotatingFileLogger {
+        level,
+        state: Mutex::new(LogFileState { path, file, written }),
+    };
+
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+        let _ = LOG_DIR.set(dir.to_path_buf());
+    }
+}
+
+/// Directory log files are written to, for "reveal in file manager" commands.
+/// `None` until `init` has run.
+pub fn log_dir() -> Option<PathBuf> {
+    LOG_DIR.get().cloned()
+}
+
+/// Snapshot of the most recent log lines (oldest first), for attaching to bug reports.
+pub fn recent_lines() -> Vec<String> {
+    RECENT_LINES
+        .lock()
+        .map(|buf| buf.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Parse a line of sidecar stdout/stderr, detecting structured JSON
+/// (`{"level": "warning", ...}`) or a textual level prefix (`[INFO] ...`,
+/// `WARN: ...`), and log it at the matching level tagged `target="sidecar"`,
+/// falling back to `default_level` for lines carrying no recognizable level.
+pub fn log_sidecar_line(line: &str, default_level: log::Level) {
+    let level = detect_level(line).unwrap_or(default_level);
+    log::log!(target: "sidecar", level, "{}", line);
+}
+
+/// Log a sidecar message whose level is already known structurally (e.g.
+/// decoded from a framed `SidecarMessage::Log`), instead of re-parsing text.
+pub fn log_sidecar_structured(level_str: &str, msg: &str) {
+    let level = parse_level(level_str).unwrap_or(log::Level::Info);
+    log::log!(target: "sidecar", level, "{}", msg);
+}
+
+fn detect_level(line: &str) -> Option<log::Level> {
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(line.trim()) {
+        if let Some(level_str) = value.get("level").and_then(|v| v.as_str()) {
+            return parse_level(level_str);
+        }
+    }
+
+    let trimmed = line.trim_start().trim_start_matches('[');
+    let prefix: String = trimmed.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    parse_level(&prefix)
+}
+
+fn parse_level(raw: &str) -> Option<log::Level> {
+    match raw.to_ascii_uppercase().as_str() {
+        "TRACE" => Some(log::Level::Trace),
+        "DEBUG" => Some(log::Level::Debug),
+        "INFO" => Some(log::Level::Info),
+        "WARN" | "WARNING" => Some(log::Level::Warn),
+        "ERROR" | "ERR" | "CRITICAL" | "FATAL" => Some(log::Level::Error),
+        _ => None,
+    }
+}
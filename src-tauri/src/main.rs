@@ -9,6 +9,18 @@ use tauri::webview::DownloadEvent;
 use tauri_plugin_shell::ShellExt;
 use tauri_plugin_shell::process::{CommandChild, CommandEvent};
 
+mod process;
+mod supervisor;
+use supervisor::RestartSupervisor;
+mod framing;
+use framing::{FrameEvent, HeaderWidth, PacketFramer, SidecarMessage};
+mod config;
+use config::SidecarConfig;
+mod integrity;
+mod pgroup;
+mod logging;
+mod archive;
+
 /// Global flag to track sidecar state
 static SIDECAR_STARTED: AtomicBool = AtomicBool::new(false);
 
@@ -27,6 +39,9 @@ static SIDECAR_CHILD: Mutex<Option<CommandChild>> = Mutex::new(None);
 /// Graceful shutdown timeout in seconds
 const GRACEFUL_SHUTDOWN_TIMEOUT_SECS: u64 = 5;
 
+/// Supervises crash detection and auto-restart of the sidecar.
+static SIDECAR_SUPERVISOR: RestartSupervisor = RestartSupervisor::new();
+
 /// Maximum file size accepted by save_file_dialog (50 MiB)
 const MAX_SAVE_FILE_BYTES: usize = 50 * 1024 * 1024;
 const MAX_SAVE_FILE_BASE64_LEN: usize = (MAX_SAVE_FILE_BYTES * 4 / 3) + 16;
@@ -128,43 +143,16 @@ fn get_available_port() -> u16 {
     port
 }
 
-/// Check if a process is still running (cross-platform)
+/// Check if a process is still running (cross-platform, via `ProcessTree`)
 fn is_process_running(pid: u32) -> bool {
-    #[cfg(unix)]
-    {
-        // On Unix, kill with signal 0 checks if process exists
-        use std::process::Command;
-        Command::new("kill")
-            .args(["-0", &pid.to_string()])
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-    }
-    #[cfg(windows)]
-    {
-        // Use tasklist to check if process exists
-        hidden_command("tasklist")
-            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
-            .output()
-            .map(|o| {
-                let output = String::from_utf8_lossy(&o.stdout);
-                output.contains(&pid.to_string())
-            })
-            .unwrap_or(false)
-    }
+    process::ProcessTree::snapshot().is_running(pid)
 }
 
-/// Send graceful termination signal (cross-platform)
-fn send_terminate_signal(pid: u32) -> bool {
+/// Send SIGTERM/WM_CLOSE-equivalent termination to a single PID (no children).
+fn terminate_single_pid(pid: u32) -> bool {
     #[cfg(unix)]
     {
         use std::process::Command;
-        // First kill child processes (PyInstaller spawns a child)
-        let _ = Command::new("pkill")
-            .args(["-15", "-P", &pid.to_string()])
-            .output();
-
-        // Then send SIGTERM to the main process
         Command::new("kill")
             .args(["-15", &pid.to_string()])
             .output()
@@ -173,26 +161,19 @@ fn send_terminate_signal(pid: u32) -> bool {
     }
     #[cfg(windows)]
     {
-        // taskkill /T terminates child processes as well
         hidden_command("taskkill")
-            .args(["/PID", &pid.to_string(), "/T"])
+            .args(["/PID", &pid.to_string()])
             .output()
             .map(|o| o.status.success())
             .unwrap_or(false)
     }
 }
 
-/// Force kill a process (cross-platform)
-fn force_kill_process(pid: u32) -> bool {
+/// Force kill a single PID (no children).
+fn force_kill_single_pid(pid: u32) -> bool {
     #[cfg(unix)]
     {
         use std::process::Command;
-        // First force kill child processes
-        let _ = Command::new("pkill")
-            .args(["-9", "-P", &pid.to_string()])
-            .output();
-
-        // Then force kill the main process
         Command::new("kill")
             .args(["-9", &pid.to_string()])
             .output()
@@ -201,63 +182,37 @@ fn force_kill_process(pid: u32) -> bool {
     }
     #[cfg(windows)]
     {
-        // taskkill with /F /T forces termination including child processes
         hidden_command("taskkill")
-            .args(["/F", "/PID", &pid.to_string(), "/T"])
+            .args(["/F", "/PID", &pid.to_string()])
             .output()
             .map(|o| o.status.success())
             .unwrap_or(false)
     }
 }
 
-/// Get child process PIDs (cross-platform)
-#[allow(unused_variables)]
-fn get_child_pids(parent_pid: u32) -> Vec<u32> {
-    #[cfg(unix)]
-    {
-        use std::process::Command;
-        // Use pgrep -P to get child PIDs
-        if let Ok(output) = Command::new("pgrep")
-            .args(["-P", &parent_pid.to_string()])
-            .output()
-        {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            return stdout
-                .lines()
-                .filter_map(|line| line.trim().parse::<u32>().ok())
-                .collect();
-        }
-        vec![]
+/// Terminate `pid` and every transitive descendant found via `ProcessTree`,
+/// leaves-first, so PyInstaller's forked grandchildren don't outlive their
+/// parent the way they did under `pkill -P`/`taskkill /T` (which only see
+/// direct children). Escalates to a force-kill for anything still alive
+/// after the signal.
+fn terminate_tree(pid: u32) {
+    let tree = process::ProcessTree::snapshot();
+    let mut victims = tree.descendants(pid);
+    victims.push(pid);
+
+    for &victim in &victims {
+        terminate_single_pid(victim);
     }
-    #[cfg(windows)]
-    {
-        // On Windows, we rely on taskkill /T to handle the tree
-        vec![]
-    }
-}
 
-/// Kill child processes of a given PID (cross-platform)
-fn kill_child_processes(parent_pid: u32) {
-    #[cfg(unix)]
-    {
-        use std::process::Command;
-        // Use pkill -P to kill all children of the parent process
-        let _ = Command::new("pkill")
-            .args(["-9", "-P", &parent_pid.to_string()])
-            .output();
-    }
-    #[cfg(windows)]
-    {
-        // On Windows, taskkill /T already handles child processes
-        // This is a fallback using wmic
-        let _ = hidden_command("wmic")
-            .args([
-                "process",
-                "where",
-                &format!("ParentProcessId={}", parent_pid),
-                "delete",
-            ])
-            .output();
+    std::thread::sleep(Duration::from_millis(500));
+
+    // Re-snapshot since PIDs may have been reaped (and in principle reused).
+    let still_alive = process::ProcessTree::snapshot();
+    for &victim in &victims {
+        if still_alive.is_running(victim) {
+            log::warn!("  Force killing PID {}...", victim);
+            force_kill_single_pid(victim);
+        }
     }
 }
 
@@ -278,81 +233,21 @@ fn force_kill_by_name() {
     }
 }
 
-/// Cleanup any leftover provisioning-station processes from previous runs (cross-platform)
+/// Cleanup any leftover provisioning-station processes from previous runs (cross-platform,
+/// via `ProcessTree` so this also works on Windows instead of being a no-op there)
 /// Returns the number of processes killed
 fn cleanup_leftover_processes() -> u32 {
     let mut killed_count = 0;
 
-    #[cfg(unix)]
-    {
-        use std::process::Command;
-
-        // Find all provisioning-station processes (exclude grep itself)
-        let output = Command::new("pgrep")
-            .args(["-f", "provisioning-station"])
-            .output();
-
-        if let Ok(output) = output {
-            let pids_str = String::from_utf8_lossy(&output.stdout);
-            for line in pids_str.lines() {
-                if let Ok(pid) = line.trim().parse::<u32>() {
-                    println!("Found leftover provisioning-station process: PID {}", pid);
-
-                    // Try graceful termination first
-                    let _ = Command::new("kill")
-                        .args(["-15", &pid.to_string()])
-                        .output();
-
-                    // Wait briefly
-                    std::thread::sleep(Duration::from_millis(500));
-
-                    // Check if still running, force kill if needed
-                    if is_process_running(pid) {
-                        println!("  Force killing PID {}...", pid);
-                        force_kill_process(pid);
-                    } else {
-                        println!("  Terminated gracefully");
-                    }
-                    killed_count += 1;
-                }
-            }
-        }
-    }
-
-    #[cfg(windows)]
-    {
-        // Use wmic to find provisioning-station processes
-        let output = hidden_command("wmic")
-            .args([
-                "process",
-                "where",
-                "name like '%provisioning-station%'",
-                "get",
-                "processid",
-            ])
-            .output();
-
-        if let Ok(output) = output {
-            let pids_str = String::from_utf8_lossy(&output.stdout);
-            for line in pids_str.lines().skip(1) {
-                // Skip header
-                if let Ok(pid) = line.trim().parse::<u32>() {
-                    println!("Found leftover provisioning-station process: PID {}", pid);
-
-                    // On Windows, just force terminate
-                    let _ = hidden_command("taskkill")
-                        .args(["/F", "/PID", &pid.to_string(), "/T"])
-                        .output();
-
-                    killed_count += 1;
-                    println!("  Terminated");
-                }
-            }
-        }
+    let leftover_pids = process::ProcessTree::snapshot().pids_matching_name("provisioning-station");
+    for pid in leftover_pids {
+        log::info!("Found leftover provisioning-station process: PID {}", pid);
+        terminate_tree(pid);
+        killed_count += 1;
     }
 
     if killed_count > 0 {
-        println!(
+        log::info!(
             "Cleaned up {} leftover provisioning-station process(es)",
             killed_count
         );
@@ -364,107 +259,48 @@ fn cleanup_leftover_processes() -> u32 {
 }
 
 /// Gracefully shutdown the sidecar with timeout
-/// Returns true if process exited gracefully, false if force killed
+///
+/// The sidecar is spawned into its own process group/session (Unix) or Job
+/// Object (Windows, see `pgroup`), so the entire descendant tree - including
+/// PyInstaller's forked helper processes - can be torn down through the one
+/// `terminate_process_tree` abstraction instead of hunting down PIDs by hand.
+/// Returns true if the tree exited on `SIGTERM`/job teardown, false if it
+/// needed the `SIGKILL` escalation.
 fn shutdown_sidecar_graceful() -> bool {
     let pid = SIDECAR_PID.swap(0, Ordering::SeqCst);
 
+    // A Tauri child handle is only set in dev mode, where the sidecar isn't in
+    // its own process group (or Job Object), so the group teardown below can't
+    // reach it; kill it through the handle instead.
+    if let Ok(mut guard) = SIDECAR_CHILD.lock() {
+        if let Some(child) = guard.take() {
+            let _ = child.kill();
+        }
+    }
+
     if pid == 0 {
-        println!("No sidecar PID to kill");
+        log::info!("No sidecar PID to kill");
         SIDECAR_STARTED.store(false, Ordering::SeqCst);
         // Fallback: kill by name in case PID wasn't recorded
         force_kill_by_name();
         return true;
     }
 
-    // IMPORTANT: Get child PIDs BEFORE killing parent, as children become orphans after
-    let child_pids = get_child_pids(pid);
-    println!("Sidecar PID: {}, child PIDs: {:?}", pid, child_pids);
-
-    // Check if process is still running
     if !is_process_running(pid) {
-        println!("Sidecar process {} already exited", pid);
-        // Kill any remaining children
-        for child_pid in &child_pids {
-            force_kill_process(*child_pid);
-        }
+        log::info!("Sidecar process {} already exited", pid);
         SIDECAR_STARTED.store(false, Ordering::SeqCst);
         return true;
     }
 
-    println!("Sending graceful termination signal to sidecar (PID: {})", pid);
+    log::info!("Terminating sidecar process tree (PID: {})", pid);
+    let graceful = pgroup::terminate_process_tree(pid, Duration::from_secs(GRACEFUL_SHUTDOWN_TIMEOUT_SECS));
 
-    // First, try to use the Tauri child handle if available
-    if let Ok(mut guard) = SIDECAR_CHILD.lock() {
-        if let Some(child) = guard.take() {
-            println!("Using Tauri child handle for graceful shutdown");
-            let _ = child.kill();
-        }
+    if !graceful {
+        log::warn!("Sidecar did not exit gracefully, SIGKILL/job teardown was needed");
     }
 
-    // Also send system-level termination signal
-    send_terminate_signal(pid);
-
-    // Kill children explicitly (they might become orphans)
-    for child_pid in &child_pids {
-        send_terminate_signal(*child_pid);
-    }
-
-    // Wait for graceful shutdown with timeout
-    let check_interval = Duration::from_millis(100);
-    let max_checks = (GRACEFUL_SHUTDOWN_TIMEOUT_SECS * 1000 / 100) as u32;
-
-    for i in 0..max_checks {
-        std::thread::sleep(check_interval);
-
-        // Check if both parent and all children have exited
-        let parent_running = is_process_running(pid);
-        let children_running: Vec<_> = child_pids.iter()
-            .filter(|&&p| is_process_running(p))
-            .collect();
-
-        if !parent_running && children_running.is_empty() {
-            println!("Sidecar and children exited gracefully after {}ms", (i + 1) * 100);
-            SIDECAR_STARTED.store(false, Ordering::SeqCst);
-            return true;
-        }
-
-        if i > 0 && i % 10 == 0 {
-            println!("Waiting for sidecar to exit... ({}ms elapsed)", (i + 1) * 100);
-        }
-    }
-
-    // Graceful shutdown timed out, force kill
-    println!(
-        "Sidecar did not exit gracefully within {}s, force killing",
-        GRACEFUL_SHUTDOWN_TIMEOUT_SECS
-    );
-
-    // Force kill children first (using saved PIDs, not pkill -P which won't work for orphans)
-    for child_pid in &child_pids {
-        println!("Force killing child PID: {}", child_pid);
-        force_kill_process(*child_pid);
-    }
-    // Then force kill the parent
-    force_kill_process(pid);
-
-    // Wait a bit more for force kill to take effect
-    for _ in 0..10 {
-        std::thread::sleep(Duration::from_millis(100));
-        let any_running = is_process_running(pid) ||
-            child_pids.iter().any(|&p| is_process_running(p));
-        if !any_running {
-            println!("Sidecar force killed successfully");
-            SIDECAR_STARTED.store(false, Ordering::SeqCst);
-            return false;
-        }
-    }
-
-    // Last resort: kill by name
-    println!("Force kill by PID failed, trying by process name");
-    force_kill_by_name();
-
     SIDECAR_STARTED.store(false, Ordering::SeqCst);
-    false
+    graceful
 }
 
 /// Tauri command to get the backend port
@@ -509,29 +345,61 @@ async fn save_file_dialog(filename: String, data: String) -> Result<String, Stri
     }
 }
 
+/// Tauri command: return the most recent in-memory log lines, so users can
+/// attach logs to a bug report without digging through the filesystem.
+#[tauri::command]
+fn get_recent_logs() -> Vec<String> {
+    logging::recent_lines()
+}
+
+/// Tauri command: open the log directory in the OS file manager.
+#[tauri::command]
+fn reveal_log_directory(handle: tauri::AppHandle) -> Result<(), String> {
+    let dir = logging::log_dir().ok_or_else(|| "log directory not initialized".to_string())?;
+    handle
+        .shell()
+        .open(dir.to_string_lossy().to_string(), None)
+        .map_err(|e| e.to_string())
+}
+
+/// Best-effort log directory, resolved without an `AppHandle` since logging
+/// is initialized before `tauri::Builder` runs (to capture pre-startup
+/// cleanup and port selection in the same log file as everything after).
+fn default_log_dir() -> std::path::PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("provisioning-station")
+        .join("logs")
+}
+
 /// Main entry point
 fn main() {
-    // Initialize logger (for both debug and release builds)
-    // In release: RUST_LOG=info ./app to see logs
-    let _ = env_logger::try_init();
+    // Rotating file logger (+ stderr), for both debug and release builds.
+    // Initialized before anything else so the leftover-process cleanup and
+    // port selection below land in the log file too.
+    logging::init(&default_log_dir(), log::LevelFilter::Info);
 
     // IMPORTANT: Clean up any leftover processes BEFORE selecting port
     // This ensures residual sidecar processes don't hold ports
     let cleaned = cleanup_leftover_processes();
     if cleaned > 0 {
-        println!("Pre-startup cleanup: removed {} leftover process(es)", cleaned);
+        log::info!("Pre-startup cleanup: removed {} leftover process(es)", cleaned);
     }
 
     let backend_port = get_available_port();
     BACKEND_PORT.store(backend_port, Ordering::SeqCst);
-    println!("Selected backend port: {}", backend_port);
     log::info!("Selected backend port: {}", backend_port);
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
-        .invoke_handler(tauri::generate_handler![get_backend_port, save_file_dialog])
+        .invoke_handler(tauri::generate_handler![
+            get_backend_port,
+            save_file_dialog,
+            get_recent_logs,
+            reveal_log_directory
+        ])
         .setup(move |app| {
             let handle = app.handle().clone();
             let port = backend_port;
@@ -659,9 +527,9 @@ fn main() {
                     api.prevent_close();
 
                     // Synchronous cleanup - blocks UI but ensures completion
-                    println!("Window close requested, initiating graceful shutdown...");
+                    log::info!("Window close requested, initiating graceful shutdown...");
                     shutdown_sidecar_graceful();
-                    println!("Shutdown complete, exiting...");
+                    log::info!("Shutdown complete, exiting...");
                     app_handle.exit(0);
                 }
                 tauri::RunEvent::ExitRequested { api, .. } => {
@@ -676,19 +544,18 @@ fn main() {
                     api.prevent_exit();
 
                     // Synchronous cleanup
-                    println!("Exit requested (Cmd+Q), initiating graceful shutdown...");
+                    log::info!("Exit requested (Cmd+Q), initiating graceful shutdown...");
                     shutdown_sidecar_graceful();
-                    println!("Shutdown complete, exiting...");
+                    log::info!("Shutdown complete, exiting...");
                     app_handle.exit(0);
                 }
                 tauri::RunEvent::Exit => {
                     // Final cleanup before exit (fallback)
-                    println!("Application exiting, final cleanup...");
+                    log::info!("Application exiting, final cleanup...");
                     let pid = SIDECAR_PID.load(Ordering::SeqCst);
                     if pid != 0 {
-                        println!("Sidecar still running at exit, force killing PID: {}", pid);
-                        kill_child_processes(pid);
-                        force_kill_process(pid);
+                        log::warn!("Sidecar still running at exit, terminating process tree for PID: {}", pid);
+                        pgroup::terminate_process_tree(pid, Duration::from_secs(1));
                     }
                 }
                 _ => {}
@@ -706,20 +573,22 @@ fn get_sidecar_cache_dir(handle: &tauri::AppHandle) -> std::path::PathBuf {
 }
 
 /// Setup sidecar bundle for PyInstaller onedir mode
-/// Extracts the sidecar executable + _internal to a cache directory outside the .app bundle
-/// This avoids PyInstaller's macOS .app bundle detection which causes path issues
+/// Extracts the sidecar executable + `_internal` archive to a cache directory
+/// outside the .app bundle. This avoids PyInstaller's macOS .app bundle
+/// detection which causes path issues.
 /// Returns the path to the extracted sidecar executable
 fn setup_sidecar_bundle(handle: &tauri::AppHandle) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
     let resource_path = handle.path().resource_dir()
         .expect("Failed to get resource directory");
 
-    // _internal is bundled via binaries/_internal/**/*
-    let internal_src = resource_path.join("binaries").join("_internal");
+    // _internal is bundled as a single tar+zstd archive via binaries/_internal.tar.zst
+    // instead of tens of thousands of loose files under binaries/_internal/**/*.
+    let internal_archive = resource_path.join("binaries").join("_internal.tar.zst");
 
-    // Check if we're in bundled mode (have _internal in resources)
-    if !internal_src.exists() {
+    // Check if we're in bundled mode (have the _internal archive in resources)
+    if !internal_archive.exists() {
         // Development mode - sidecar runs from src-tauri/binaries/ directly
-        log::info!("_internal not found in resources (development mode)");
+        log::info!("_internal.tar.zst not found in resources (development mode)");
         // Return None to indicate we should use normal sidecar path
         return Err("Development mode - use normal sidecar".into());
     }
@@ -727,7 +596,6 @@ fn setup_sidecar_bundle(handle: &tauri::AppHandle) -> Result<std::path::PathBuf,
     // Get cache directory
     let cache_dir = get_sidecar_cache_dir(handle);
     let extracted_exe = cache_dir.join(get_sidecar_exe_name());
-    let extracted_internal = cache_dir.join("_internal");
 
     // Find the source sidecar executable
     let app_exe = std::env::current_exe()?;
@@ -738,67 +606,97 @@ fn setup_sidecar_bundle(handle: &tauri::AppHandle) -> Result<std::path::PathBuf,
     let sidecar_name = "provisioning-station";
     let sidecar_src = macos_dir.join(sidecar_name);
 
-    // Check if already extracted and version matches
-    // Compare file sizes to detect version mismatch (size change indicates new build)
-    let needs_extraction = if extracted_exe.exists() && extracted_internal.exists() {
-        // Check if source and extracted exe have same size (simple version check)
-        let src_size = std::fs::metadata(&sidecar_src).map(|m| m.len()).unwrap_or(0);
-        let dst_size = std::fs::metadata(&extracted_exe).map(|m| m.len()).unwrap_or(0);
-        if src_size != dst_size {
-            log::info!("Sidecar version mismatch detected (size: {} vs {}), re-extracting", src_size, dst_size);
-            true
-        } else {
-            log::info!("Sidecar already extracted at {:?} (version matches)", cache_dir);
-            false
-        }
-    } else {
-        true
-    };
-
-    if !needs_extraction {
-        return Ok(extracted_exe);
-    }
-
     // Verify source sidecar exists
     if !sidecar_src.exists() {
         return Err(format!("Sidecar executable not found at {:?}", sidecar_src).into());
     }
 
-    log::info!("Extracting sidecar bundle to {:?}", cache_dir);
-    log::info!("Found sidecar at: {:?}", sidecar_src);
-
-    // Create cache directory
-    std::fs::create_dir_all(&cache_dir)?;
-
-    // Copy sidecar executable
-    log::info!("Copying sidecar executable to: {:?}", extracted_exe);
-    std::fs::copy(&sidecar_src, &extracted_exe)?;
-
-    // Set executable permission on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(&extracted_exe)?.permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(&extracted_exe, perms)?;
+    // Re-extract if there's no manifest yet, the source executable's hash
+    // changed, the `_internal` archive's hash changed, or someone deleted
+    // the extracted `_internal` directory by hand.
+    let needs_extraction = integrity::needs_extraction(&cache_dir, &sidecar_src, &internal_archive)
+        || integrity::internal_dir_missing(&cache_dir);
+    if needs_extraction {
+        log::info!("Sidecar bundle missing or out of date, extracting to {:?}", cache_dir);
+        extract_sidecar_bundle(handle, &cache_dir, &sidecar_src, &internal_archive)?;
+        return Ok(extracted_exe);
     }
 
-    // Copy _internal directory
-    if extracted_internal.exists() {
-        log::info!("Removing old _internal directory");
-        std::fs::remove_dir_all(&extracted_internal)?;
+    // Manifest matches the source exe's hash - but re-verify the executable
+    // that's actually on disk before launching, and repair it if it drifted
+    // (e.g. a user truncating it under the cache dir by hand). `_internal`'s
+    // contents are trusted once the archive hash matches; see
+    // `integrity::Manifest::internal_archive_hash`.
+    if let Some(manifest) = integrity::read_manifest(&cache_dir) {
+        let mismatches = integrity::find_mismatches(&cache_dir, &manifest);
+        if !mismatches.is_empty() {
+            log::warn!(
+                "Sidecar bundle has {} corrupted/missing file(s), re-extracting: {:?}",
+                mismatches.len(),
+                mismatches
+            );
+            extract_sidecar_bundle(handle, &cache_dir, &sidecar_src, &internal_archive)?;
+            return Ok(extracted_exe);
+        }
     }
-    log::info!("Copying _internal directory to: {:?}", extracted_internal);
-    copy_dir_recursive(&internal_src, &extracted_internal)?;
 
-    // Set executable permissions on dynamic libraries (Unix)
-    #[cfg(unix)]
-    {
-        set_dylib_permissions(&extracted_internal)?;
-    }
+    log::info!("Sidecar already extracted at {:?} (hashes verified)", cache_dir);
+    Ok(extracted_exe)
+}
+
+/// Extract the sidecar executable and the `_internal.tar.zst` archive into a
+/// staging directory, then atomically swap the staging directory into
+/// `cache_dir`'s place. A kill at any point before the final rename leaves
+/// either the previous bundle or no bundle at `cache_dir`, never a
+/// half-populated one.
+///
+/// Emits `sidecar-extract-progress` events on `handle`'s main window while
+/// `_internal` streams out, so the splash screen can show a real progress
+/// bar instead of an unexplained wait during first-run unpacking.
+fn extract_sidecar_bundle(
+    handle: &tauri::AppHandle,
+    cache_dir: &std::path::Path,
+    sidecar_src: &std::path::Path,
+    internal_archive: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let source_exe_hash = integrity::hash_file(sidecar_src)?;
+    let internal_archive_hash = integrity::hash_file(internal_archive)?;
+
+    integrity::extract_atomic(cache_dir, |staging_dir| {
+        let mut manifest = integrity::Manifest {
+            source_exe_hash: source_exe_hash.clone(),
+            internal_archive_hash: internal_archive_hash.clone(),
+            files: Default::default(),
+        };
+
+        let staged_exe = staging_dir.join(get_sidecar_exe_name());
+        log::info!("Copying sidecar executable to: {:?}", staged_exe);
+        std::fs::copy(sidecar_src, &staged_exe)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&staged_exe)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&staged_exe, perms)?;
+        }
+        integrity::record_file(
+            &mut manifest,
+            std::path::PathBuf::from(get_sidecar_exe_name()),
+            &staged_exe,
+        )?;
+
+        let staged_internal = staging_dir.join("_internal");
+        log::info!("Extracting _internal archive to: {:?}", staged_internal);
+        archive::extract(internal_archive, &staged_internal, |progress| {
+            emit_sidecar_event(handle, "sidecar-extract-progress", progress);
+        })
+        .map_err(|e| std::io::Error::new(e.kind(), format!("extracting _internal archive: {e}")))?;
+
+        Ok(manifest)
+    })?;
 
     log::info!("Sidecar bundle extracted successfully");
-    Ok(extracted_exe)
+    Ok(())
 }
 
 /// Get the sidecar executable name (platform-specific)
@@ -809,49 +707,37 @@ fn get_sidecar_exe_name() -> &'static str {
     { "provisioning-station" }
 }
 
-/// Set executable permissions on dynamic libraries recursively
-#[cfg(unix)]
-fn set_dylib_permissions(dir: &std::path::Path) -> std::io::Result<()> {
-    use std::os::unix::fs::PermissionsExt;
-
-    for entry in std::fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            set_dylib_permissions(&path)?;
-        } else {
-            let name = path.file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default();
-
-            // Set executable for .so, .dylib files
-            if name.ends_with(".so") || name.contains(".so.") || name.ends_with(".dylib") {
-                let mut perms = std::fs::metadata(&path)?.permissions();
-                perms.set_mode(0o755);
-                std::fs::set_permissions(&path, perms)?;
-            }
-        }
+/// How long to wait for the backend to become healthy, and how often to poll
+/// while waiting. Separated from `SidecarConfig` since it's purely a retry
+/// policy, not launch configuration.
+#[derive(Debug, Clone, Copy)]
+struct ReadyRetryPolicy {
+    total_timeout: Duration,
+    poll_interval: Duration,
+}
+
+/// Abstraction over "ask the backend once whether it's healthy", so the
+/// readiness loop can be driven by a mock HTTP server in tests instead of a
+/// real `reqwest::Client` hitting the real sidecar.
+trait HealthProbe: Send + Sync {
+    fn check<'a>(&'a self, url: &'a str) -> std::pin::Pin<Box<dyn std::future::Future<Output = bool> + Send + 'a>>;
+}
+
+/// Production [`HealthProbe`]: a real GET request, successful only on a 2xx response.
+struct ReqwestProbe(reqwest::Client);
+
+impl ReqwestProbe {
+    fn new(attempt_timeout: Duration) -> Result<Self, reqwest::Error> {
+        Ok(Self(reqwest::Client::builder().timeout(attempt_timeout).build()?))
     }
-    Ok(())
 }
 
-/// Recursively copy a directory
-fn copy_dir_recursive(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<()> {
-    std::fs::create_dir_all(dst)?;
-    for entry in std::fs::read_dir(src)? {
-        let entry = entry?;
-        let ty = entry.file_type()?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-
-        if ty.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            std::fs::copy(&src_path, &dst_path)?;
-        }
+impl HealthProbe for ReqwestProbe {
+    fn check<'a>(&'a self, url: &'a str) -> std::pin::Pin<Box<dyn std::future::Future<Output = bool> + Send + 'a>> {
+        Box::pin(async move {
+            matches!(self.0.get(url).send().await, Ok(response) if response.status().is_success())
+        })
     }
-    Ok(())
 }
 
 /// Start the Python backend sidecar
@@ -871,9 +757,13 @@ async fn start_sidecar(
         log::warn!("Late cleanup: removed {} process(es) spawned after initial cleanup", cleaned);
     }
 
-    println!("Starting provisioning-station sidecar on port {}", port);
     log::info!("Starting provisioning-station sidecar on port {}", port);
 
+    // Extra env/cwd/readiness-timeout overrides, instead of just inheriting
+    // whatever environment the app happens to have. Fail fast on malformed
+    // entries - the caller surfaces this error on the splash screen.
+    let sidecar_config = SidecarConfig::from_env()?;
+
     // Get the resource directory where solutions are bundled
     let resource_path = handle.path().resource_dir()
         .expect("Failed to get resource directory");
@@ -903,6 +793,12 @@ async fn start_sidecar(
         args.push(frontend_dir.to_string_lossy().to_string());
     }
 
+    // Ask the sidecar to additionally emit a framed, length-prefixed lifecycle
+    // channel on stdout (4-byte big-endian length header + JSON payload) so we
+    // can decode typed `SidecarMessage`s instead of pattern-matching log text.
+    args.push("--packet-mode".to_string());
+    args.push("4".to_string());
+
     // Try to setup and use extracted sidecar (PyInstaller onedir mode)
     // This extracts sidecar + _internal to a cache directory outside the .app bundle
     // which avoids PyInstaller's macOS bundle detection issues
@@ -917,6 +813,7 @@ async fn start_sidecar(
             cmd.args(&args)
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped());
+            sidecar_config.apply_to(&mut cmd);
 
             // Hide console window on Windows
             #[cfg(windows)]
@@ -925,9 +822,21 @@ async fn start_sidecar(
                 cmd.creation_flags(CREATE_NO_WINDOW);
             }
 
+            // Detach into its own session/process group so the whole tree
+            // PyInstaller spawns can be signaled as a unit on shutdown.
+            #[cfg(unix)]
+            unsafe {
+                pgroup::unix::detach_into_new_session(&mut cmd);
+            }
+
             let mut process = cmd.spawn()?;
             let pid = process.id();
 
+            // Assign to a kill-on-close Job Object so the whole tree goes
+            // down when we tear the job down, mirroring the Unix pgid above.
+            #[cfg(windows)]
+            pgroup::windows::assign_to_job(pid);
+
             // Store PID for cleanup on exit
             SIDECAR_PID.store(pid, Ordering::SeqCst);
             log::info!("Sidecar spawned with PID: {}", pid);
@@ -937,13 +846,54 @@ async fn start_sidecar(
             let stdout = process.stdout.take();
             let stderr = process.stderr.take();
 
-            if let Some(stdout) = stdout {
+            // stdout carries the framed `{packet, 4}` lifecycle channel requested via
+            // --packet-mode above: accumulate raw bytes (a packet can span multiple
+            // reads) and decode each complete frame into a typed `SidecarMessage`
+            // instead of pattern-matching substrings in plain text. Any stray
+            // unframed bytes that leak onto the same stream (a PyInstaller
+            // bootloader line, a traceback) come back as `FrameEvent::RawLine`
+            // instead of being misread as a bogus length header, and are logged
+            // the same way stderr lines are.
+            if let Some(mut stdout) = stdout {
+                let stdout_handle = handle.clone();
                 std::thread::spawn(move || {
-                    use std::io::BufRead;
-                    let reader = std::io::BufReader::new(stdout);
-                    for line in reader.lines() {
-                        if let Ok(line) = line {
-                            log::info!("[sidecar] {}", line);
+                    use std::io::Read;
+                    let mut framer = PacketFramer::new(HeaderWidth::Four);
+                    let mut chunk = [0u8; 4096];
+                    loop {
+                        match stdout.read(&mut chunk) {
+                            Ok(0) => break, // EOF
+                            Ok(n) => match framer.feed(&chunk[..n]) {
+                                Ok(events) => {
+                                    for event in events {
+                                        let message = match event {
+                                            FrameEvent::RawLine(line) => {
+                                                logging::log_sidecar_line(&line, log::Level::Info);
+                                                continue;
+                                            }
+                                            FrameEvent::MalformedPacket(e) => {
+                                                log::warn!("[sidecar] malformed packet frame: {}", e);
+                                                continue;
+                                            }
+                                            FrameEvent::Message(message) => message,
+                                        };
+                                        match &message {
+                                            SidecarMessage::Log { level, msg } => {
+                                                logging::log_sidecar_structured(level, msg);
+                                            }
+                                            other => log::info!("[sidecar] {:?}", other),
+                                        }
+                                        if let Some(win) = stdout_handle.get_webview_window("main") {
+                                            let _ = win.emit("sidecar-message", &message);
+                                        }
+                                    }
+                                }
+                                Err(e) => log::warn!("[sidecar] malformed packet frame: {}", e),
+                            },
+                            Err(e) => {
+                                log::warn!("[sidecar] stdout read error: {}", e);
+                                break;
+                            }
                         }
                     }
                 });
@@ -955,29 +905,49 @@ async fn start_sidecar(
                     let reader = std::io::BufReader::new(stderr);
                     for line in reader.lines() {
                         if let Ok(line) = line {
-                            log::warn!("[sidecar] {}", line);
+                            logging::log_sidecar_line(&line, log::Level::Warn);
                         }
                     }
                 });
             }
 
-            // Wait for process to exit in background and update state
+            // Wait for process to exit in background and update state. Mirrors
+            // the dev-mode `CommandEvent::Terminated` handling below: an
+            // unexpected exit (sidecar was up, and we're not the ones tearing
+            // it down) kicks off the same restart supervisor so a bundled
+            // crash doesn't leave the app silently dead.
+            let restart_handle = handle.clone();
             std::thread::spawn(move || {
                 let _ = process.wait();
                 log::info!("Sidecar process exited");
-                SIDECAR_STARTED.store(false, Ordering::SeqCst);
+                let was_started = SIDECAR_STARTED.swap(false, Ordering::SeqCst);
                 SIDECAR_PID.store(0, Ordering::SeqCst);
+
+                if was_started && !SHUTDOWN_IN_PROGRESS.load(Ordering::SeqCst) {
+                    let generation = SIDECAR_SUPERVISOR.begin_restart_cycle();
+                    tauri::async_runtime::spawn(supervise_sidecar_restart(
+                        restart_handle,
+                        port,
+                        generation,
+                    ));
+                }
             });
         }
         Err(_) => {
             // Development mode - use Tauri's sidecar mechanism
             log::info!("Using Tauri sidecar (development mode)");
 
-            let sidecar = handle
+            let mut sidecar = handle
                 .shell()
                 .sidecar("provisioning-station")
                 .expect("Failed to create sidecar command")
                 .args(&args);
+            if let Some(dir) = &sidecar_config.working_dir {
+                sidecar = sidecar.current_dir(dir.clone());
+            }
+            if !sidecar_config.env.is_empty() {
+                sidecar = sidecar.envs(sidecar_config.env.iter().cloned().collect());
+            }
 
             let (mut rx, child) = sidecar.spawn()?;
 
@@ -994,21 +964,33 @@ async fn start_sidecar(
             SIDECAR_STARTED.store(true, Ordering::SeqCst);
 
             // Handle sidecar output in background
+            let restart_handle = handle.clone();
             tauri::async_runtime::spawn(async move {
                 while let Some(event) = rx.recv().await {
                     match event {
                         CommandEvent::Stdout(line) => {
                             let line_str = String::from_utf8_lossy(&line);
-                            log::info!("[sidecar] {}", line_str);
+                            logging::log_sidecar_line(&line_str, log::Level::Info);
                         }
                         CommandEvent::Stderr(line) => {
                             let line_str = String::from_utf8_lossy(&line);
-                            log::warn!("[sidecar] {}", line_str);
+                            logging::log_sidecar_line(&line_str, log::Level::Warn);
                         }
                         CommandEvent::Terminated(status) => {
                             log::info!("Sidecar terminated with status: {:?}", status);
-                            SIDECAR_STARTED.store(false, Ordering::SeqCst);
+                            let was_started = SIDECAR_STARTED.swap(false, Ordering::SeqCst);
                             SIDECAR_PID.store(0, Ordering::SeqCst);
+
+                            // Only auto-restart if the sidecar was up and this wasn't
+                            // us tearing it down on purpose.
+                            if was_started && !SHUTDOWN_IN_PROGRESS.load(Ordering::SeqCst) {
+                                let generation = SIDECAR_SUPERVISOR.begin_restart_cycle();
+                                tauri::async_runtime::spawn(supervise_sidecar_restart(
+                                    restart_handle.clone(),
+                                    port,
+                                    generation,
+                                ));
+                            }
                             break;
                         }
                         CommandEvent::Error(err) => {
@@ -1021,32 +1003,89 @@ async fn start_sidecar(
         }
     }
 
-    // Wait for backend to be ready
-    let health_url = format!("http://127.0.0.1:{}/api/health", port);
-    let client = reqwest::Client::new();
-
+    // Wait for backend to be ready: bounded by a total deadline rather than a
+    // fixed attempt count, with each individual request capped at
+    // `ready_attempt_timeout` so a hung connection can't eat the whole budget.
+    //
     // First cold start (fresh install) can be slow due to:
-    //   - Sidecar extraction (~55MB _internal directory)
+    //   - Sidecar extraction (_internal archive decompression)
     //   - PyInstaller first-run module loading
     //   - macOS Gatekeeper scanning new binaries
-    // Use generous timeout: 60 attempts Ã— 500ms = 30 seconds
-    for attempt in 1..=60 {
-        match client.get(&health_url).send().await {
-            Ok(response) if response.status().is_success() => {
-                log::info!("Backend is ready (attempt {})", attempt);
-                return Ok(());
-            }
-            _ => {
-                if attempt % 10 == 0 {
-                    log::info!("Waiting for backend to start... (attempt {}/60)", attempt);
-                }
-                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-            }
+    let health_url = format!("http://127.0.0.1:{}/api/health", port);
+    let probe = ReqwestProbe::new(sidecar_config.ready_attempt_timeout)?;
+    let policy = ReadyRetryPolicy {
+        total_timeout: sidecar_config.ready_total_timeout,
+        poll_interval: Duration::from_millis(500),
+    };
+
+    // The sidecar process was already spawned above, so all this callback
+    // needs to do is report that - it exists so tests can plug in a fake
+    // spawn (or a failing one) instead of launching the real sidecar.
+    match run_readiness_sequence(|| Ok(()), &probe, &health_url, policy, cleanup_after_ready_timeout)
+        .await
+    {
+        Ok(attempt) => {
+            log::info!("Backend is ready (attempt {})", attempt);
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Poll for a successful response on `health_url`, bounded by
+/// `policy.total_timeout` with `policy.poll_interval` between attempts.
+/// Re-emits "attempt N" progress every 10th attempt, same as the fixed loop
+/// this replaced.
+async fn poll_until_ready(
+    probe: &dyn HealthProbe,
+    health_url: &str,
+    policy: ReadyRetryPolicy,
+) -> Result<u32, u32> {
+    let deadline = std::time::Instant::now() + policy.total_timeout;
+    let mut attempt = 0u32;
+    while std::time::Instant::now() < deadline {
+        attempt += 1;
+        if probe.check(health_url).await {
+            return Ok(attempt);
+        }
+        if attempt % 10 == 0 {
+            log::info!("Waiting for backend to start... (attempt {})", attempt);
         }
+        tokio::time::sleep(policy.poll_interval).await;
     }
+    Err(attempt)
+}
 
-    // Backend never became healthy: clean up the spawned sidecar to avoid
-    // leaving an orphan process occupying the selected port.
+/// The sidecar startup sequence - spawn, then poll for readiness, then clean
+/// up on timeout - pulled out of `start_sidecar` and parameterized over a
+/// spawn callback, a [`HealthProbe`], and a [`ReadyRetryPolicy`] so it can be
+/// exercised against a mock HTTP server in tests instead of the real Python
+/// sidecar. `on_timeout` runs exactly once, and only if the deadline passes
+/// without a successful probe.
+async fn run_readiness_sequence(
+    spawn: impl FnOnce() -> Result<(), String>,
+    probe: &dyn HealthProbe,
+    health_url: &str,
+    policy: ReadyRetryPolicy,
+    on_timeout: impl FnOnce(),
+) -> Result<u32, String> {
+    spawn()?;
+
+    match poll_until_ready(probe, health_url, policy).await {
+        Ok(attempt) => Ok(attempt),
+        Err(attempt) => {
+            on_timeout();
+            Err(format!(
+                "Backend failed to start within timeout after {attempt} attempt(s)"
+            ))
+        }
+    }
+}
+
+/// Tear down a sidecar that never became healthy: terminate the process tree
+/// (if we have a PID) and reset the shared `SIDECAR_STARTED`/`SIDECAR_CHILD`
+/// state, so a failed launch doesn't leave stale state blocking a retry.
+fn cleanup_after_ready_timeout() {
     log::error!("Backend health check timed out, cleaning up sidecar");
     if SIDECAR_PID.load(Ordering::SeqCst) != 0 {
         shutdown_sidecar_graceful();
@@ -1055,8 +1094,74 @@ async fn start_sidecar(
     if let Ok(mut guard) = SIDECAR_CHILD.lock() {
         *guard = None;
     }
+}
+
+/// Emit a lifecycle event on the main window, for the splash/overlay to react to.
+fn emit_sidecar_event(handle: &tauri::AppHandle, event: &str, payload: impl serde::Serialize) {
+    if let Some(win) = handle.get_webview_window("main") {
+        let _ = win.emit(event, payload);
+    }
+}
 
-    Err("Backend failed to start within timeout".into())
+/// Restart the sidecar after an unexpected crash, with exponential backoff.
+///
+/// Bails out immediately if `generation` has been superseded by a newer crash
+/// (i.e. `SIDECAR_SUPERVISOR` has already moved on), so a stale termination
+/// event can't race a restart that's already in flight.
+async fn supervise_sidecar_restart(handle: tauri::AppHandle, port: u16, generation: u32) {
+    let policy = supervisor::BackoffPolicy::default();
+
+    while SIDECAR_SUPERVISOR.is_current(generation) {
+        let attempt = SIDECAR_SUPERVISOR.record_failure();
+        if attempt > policy.max_attempts {
+            log::error!("Sidecar crashed {} times in a row, giving up", attempt - 1);
+            emit_sidecar_event(&handle, "sidecar-failed", attempt - 1);
+            return;
+        }
+
+        let delay = supervisor::backoff_delay(attempt, &policy);
+        log::warn!(
+            "Sidecar crashed unexpectedly, restarting in {:?} (attempt {}/{})",
+            delay, attempt, policy.max_attempts
+        );
+        emit_sidecar_event(&handle, "sidecar-restarting", attempt);
+        tokio::time::sleep(delay).await;
+
+        if !SIDECAR_SUPERVISOR.is_current(generation) {
+            log::info!("Restart generation {} superseded, aborting", generation);
+            return;
+        }
+
+        match start_sidecar(&handle, port).await {
+            Ok(()) => {
+                log::info!("Sidecar recovered after {} attempt(s)", attempt);
+                emit_sidecar_event(&handle, "sidecar-recovered", attempt);
+
+                if let Some(win) = handle.get_webview_window("main") {
+                    let backend_url = format!("http://127.0.0.1:{}", port);
+                    let _ = win.navigate(backend_url.parse().unwrap());
+                }
+
+                // Reset the failure streak once the sidecar has proven stable,
+                // unless another crash (and thus a newer generation) beat us to it.
+                let reset_handle = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(policy.reset_after).await;
+                    if SIDECAR_SUPERVISOR.is_current(generation)
+                        && SIDECAR_STARTED.load(Ordering::SeqCst)
+                    {
+                        SIDECAR_SUPERVISOR.reset_failures();
+                        log::debug!("Sidecar stable, restart backoff reset");
+                    }
+                    drop(reset_handle);
+                });
+                return;
+            }
+            Err(e) => {
+                log::error!("Sidecar restart attempt {} failed: {}", attempt, e);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1109,4 +1214,85 @@ mod tests {
         assert!(!should_open_external_browser("tauri", Some("localhost")));
         assert!(!should_open_external_browser("file", None));
     }
+
+    /// A tiny in-process HTTP/1.1 server bound to `127.0.0.1:0`, returning
+    /// `statuses[i]` (clamped to the last entry once exhausted) for the i-th
+    /// request it accepts. Exercises `run_readiness_sequence` against real
+    /// sockets instead of launching the real Python sidecar.
+    fn spawn_mock_health_server(statuses: Vec<u16>) -> std::net::SocketAddr {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut i = 0usize;
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let status = statuses.get(i).copied().unwrap_or(*statuses.last().unwrap());
+                i += 1;
+
+                use std::io::{Read, Write};
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+
+                let reason = if status == 200 { "OK" } else { "Service Unavailable" };
+                let response =
+                    format!("HTTP/1.1 {status} {reason}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn readiness_loop_waits_out_503s_then_succeeds() {
+        let addr = spawn_mock_health_server(vec![503, 503, 503, 200]);
+        let url = format!("http://{}/api/health", addr);
+        let probe = ReqwestProbe::new(Duration::from_millis(200)).unwrap();
+        let policy = ReadyRetryPolicy {
+            total_timeout: Duration::from_secs(5),
+            poll_interval: Duration::from_millis(20),
+        };
+        let timeouts = std::sync::Arc::new(AtomicU32::new(0));
+        let timeouts_clone = timeouts.clone();
+
+        let result = run_readiness_sequence(
+            || Ok(()),
+            &probe,
+            &url,
+            policy,
+            move || {
+                timeouts_clone.fetch_add(1, Ordering::SeqCst);
+            },
+        )
+        .await;
+
+        assert!(matches!(result, Ok(attempt) if attempt >= 4));
+        assert_eq!(timeouts.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn readiness_loop_times_out_and_runs_cleanup_exactly_once() {
+        let addr = spawn_mock_health_server(vec![503]);
+        let url = format!("http://{}/api/health", addr);
+        let probe = ReqwestProbe::new(Duration::from_millis(50)).unwrap();
+        let policy = ReadyRetryPolicy {
+            total_timeout: Duration::from_millis(150),
+            poll_interval: Duration::from_millis(20),
+        };
+        let timeouts = std::sync::Arc::new(AtomicU32::new(0));
+        let timeouts_clone = timeouts.clone();
+
+        let result = run_readiness_sequence(
+            || Ok(()),
+            &probe,
+            &url,
+            policy,
+            move || {
+                timeouts_clone.fetch_add(1, Ordering::SeqCst);
+            },
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(timeouts.load(Ordering::SeqCst), 1);
+    }
 }
@@ -0,0 +1,272 @@
+//! Helpers for waiting on child processes without busy-polling.
+//!
+//! `shutdown_sidecar_graceful` used to detect process exit by shelling out to
+//! `kill -0`/`tasklist` every 100ms, which spawns a short-lived process per
+//! check and can be fooled by PID reuse once the real child has exited. This
+//! module replaces that with a blocking wait on the actual child, modeled on
+//! std's old `Child::wait_timeout` design: a dedicated reaper thread reaps the
+//! child with `waitpid` each time SIGCHLD wakes it (Unix) or blocks in
+//! `WaitForSingleObject` (Windows), and wakes a condvar the caller is waiting
+//! on, so the timeout is exact and we never race against a recycled PID.
+
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Condvar, Mutex, Once};
+use std::time::Duration;
+
+/// Outcome of waiting for a child PID to exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitOutcome {
+    /// The process was reaped before the deadline elapsed.
+    Exited,
+    /// The deadline elapsed with the process still (believed to be) running.
+    TimedOut,
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use std::io::Read;
+    use std::os::unix::io::{FromRawFd, RawFd};
+
+    // Self-pipe that the SIGCHLD handler writes a single byte into. The reaper
+    // thread `poll`s this fd with a short timeout, so it wakes promptly on a
+    // child exit instead of polling on a fixed clock.
+    static SELF_PIPE_WRITE: AtomicI32 = AtomicI32::new(-1);
+    static INSTALL_HANDLER: Once = Once::new();
+
+    extern "C" fn sigchld_handler(_sig: libc::c_int) {
+        let fd = SELF_PIPE_WRITE.load(Ordering::SeqCst);
+        if fd >= 0 {
+            let byte: [u8; 1] = [0];
+            // write(2) is async-signal-safe; ignore errors (e.g. EAGAIN on a full pipe).
+            unsafe {
+                libc::write(fd, byte.as_ptr() as *const libc::c_void, 1);
+            }
+        }
+    }
+
+    fn ensure_sigchld_handler() -> RawFd {
+        INSTALL_HANDLER.call_once(|| unsafe {
+            let mut fds = [0 as RawFd; 2];
+            if libc::pipe(fds.as_mut_ptr()) != 0 {
+                log::error!("process: failed to create self-pipe for SIGCHLD");
+                return;
+            }
+            let (read_fd, write_fd) = (fds[0], fds[1]);
+            let read_flags = libc::fcntl(read_fd, libc::F_GETFL);
+            libc::fcntl(read_fd, libc::F_SETFL, read_flags | libc::O_NONBLOCK);
+
+            SELF_PIPE_WRITE.store(write_fd, Ordering::SeqCst);
+            SELF_PIPE_READ.store(read_fd, Ordering::SeqCst);
+
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = sigchld_handler as usize;
+            libc::sigemptyset(&mut action.sa_mask);
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigaction(libc::SIGCHLD, &action, std::ptr::null_mut());
+        });
+        SELF_PIPE_READ.load(Ordering::SeqCst)
+    }
+
+    static SELF_PIPE_READ: AtomicI32 = AtomicI32::new(-1);
+
+    /// Block until `pid` has been reaped or `timeout` elapses.
+    ///
+    /// Spawns a reaper thread that waits on the SIGCHLD self-pipe for at most
+    /// 20ms at a time (a plain 20ms sleep if the pipe couldn't be set up) and calls
+    /// `waitpid(pid, WNOHANG)` whenever it wakes, signalling a condvar once
+    /// the child is gone.
+    pub fn wait_for_exit(pid: u32, timeout: Duration) -> WaitOutcome {
+        let read_fd = ensure_sigchld_handler();
+        let pair = std::sync::Arc::new((Mutex::new(false), Condvar::new()));
+        let reaper_pair = pair.clone();
+
+        let handle = std::thread::spawn(move || {
+            let deadline = std::time::Instant::now() + timeout;
+            loop {
+                // Reap-or-gone check first: the child may already have exited
+                // before we even started waiting.
+                let status = unsafe { libc::waitpid(pid as libc::pid_t, std::ptr::null_mut(), libc::WNOHANG) };
+                // `std::io::Error::last_os_error()` reads errno portably; raw
+                // `libc::__errno_location()` is glibc-only and doesn't exist
+                // on Apple targets (which expose `__error()` instead), so it
+                // would fail to build on macOS.
+                if status == pid as libc::c_int
+                    || (status == -1
+                        && std::io::Error::last_os_error().raw_os_error() == Some(libc::ECHILD))
+                {
+                    let (lock, cvar) = &*reaper_pair;
+                    let mut done = lock.lock().unwrap();
+                    *done = true;
+                    cvar.notify_all();
+                    return;
+                }
+
+                if std::time::Instant::now() >= deadline {
+                    return;
+                }
+
+                // Wait for a SIGCHLD wakeup (then drain the self-pipe) or a short
+                // timeout so we still notice the deadline.
+                if read_fd >= 0 {
+                    let mut pfd = libc::pollfd { fd: read_fd, events: libc::POLLIN, revents: 0 };
+                    unsafe { libc::poll(&mut pfd, 1, 20) };
+                    let mut buf = [0u8; 64];
+                    let mut file = unsafe { std::fs::File::from_raw_fd(read_fd) };
+                    let _ = file.read(&mut buf);
+                    std::mem::forget(file); // we don't own the fd, just borrowed it for the read
+                } else {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+            }
+        });
+
+        let (lock, cvar) = &*pair;
+        let guard = lock.lock().unwrap();
+        let (_guard, wait_result) = cvar
+            .wait_timeout_while(guard, timeout, |done| !*done)
+            .unwrap();
+
+        let _ = handle.join();
+
+        if wait_result.timed_out() {
+            WaitOutcome::TimedOut
+        } else {
+            WaitOutcome::Exited
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::*;
+    use std::os::windows::io::RawHandle;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn OpenProcess(dwDesiredAccess: u32, bInheritHandle: i32, dwProcessId: u32) -> RawHandle;
+        fn WaitForSingleObject(hHandle: RawHandle, dwMilliseconds: u32) -> u32;
+        fn CloseHandle(hObject: RawHandle) -> i32;
+    }
+
+    const SYNCHRONIZE: u32 = 0x00100000;
+    const WAIT_OBJECT_0: u32 = 0x0;
+    const WAIT_TIMEOUT: u32 = 0x102;
+
+    /// Block until `pid` exits or `timeout` elapses, via `WaitForSingleObject`
+    /// on a handle opened with just `SYNCHRONIZE` rights.
+    pub fn wait_for_exit(pid: u32, timeout: Duration) -> WaitOutcome {
+        let handle = unsafe { OpenProcess(SYNCHRONIZE, 0, pid) };
+        if handle.is_null() {
+            // Process already gone (or inaccessible) - treat as exited.
+            return WaitOutcome::Exited;
+        }
+
+        let pair = std::sync::Arc::new((Mutex::new(false), Condvar::new()));
+        let reaper_pair = pair.clone();
+        let millis = timeout.as_millis().min(u32::MAX as u128) as u32;
+
+        let handle_val = handle as usize;
+        let reaper = std::thread::spawn(move || {
+            let result = unsafe { WaitForSingleObject(handle_val as RawHandle, millis) };
+            if result == WAIT_OBJECT_0 {
+                let (lock, cvar) = &*reaper_pair;
+                let mut done = lock.lock().unwrap();
+                *done = true;
+                cvar.notify_all();
+            }
+        });
+
+        let (lock, cvar) = &*pair;
+        let guard = lock.lock().unwrap();
+        let (_guard, wait_result) = cvar
+            .wait_timeout_while(guard, timeout, |done| !*done)
+            .unwrap();
+
+        let _ = reaper.join();
+        unsafe { CloseHandle(handle) };
+
+        if wait_result.timed_out() {
+            WaitOutcome::TimedOut
+        } else {
+            WaitOutcome::Exited
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use unix::wait_for_exit;
+#[cfg(windows)]
+pub use windows::wait_for_exit;
+
+/// A snapshot of the OS process table, used to walk process trees.
+///
+/// `pgrep -P`/`wmic ParentProcessId=` only see *direct* children, so
+/// PyInstaller's nested worker processes (grandchildren of our sidecar PID)
+/// were leaking on shutdown, and Windows got no tree tracking at all since we
+/// never forked an equivalent of `pgrep` there. `sysinfo` gives us one
+/// in-process table we can refresh and walk via `parent()` links on every
+/// platform, the same way zellij tracks its pane processes.
+pub struct ProcessTree {
+    system: sysinfo::System,
+}
+
+impl ProcessTree {
+    /// Take a fresh snapshot of the system process table.
+    pub fn snapshot() -> Self {
+        let mut system = sysinfo::System::new();
+        system.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+        Self { system }
+    }
+
+    /// Whether `pid` exists in this snapshot.
+    pub fn is_running(&self, pid: u32) -> bool {
+        self.system.process(sysinfo::Pid::from_u32(pid)).is_some()
+    }
+
+    /// Collect every transitive descendant of `pid` (children, grandchildren, ...),
+    /// ordered leaves-first so a caller can kill children before parents.
+    pub fn descendants(&self, pid: u32) -> Vec<u32> {
+        let root = sysinfo::Pid::from_u32(pid);
+        let mut by_depth: Vec<(u32, u32)> = Vec::new(); // (pid, depth from root)
+
+        for (candidate_pid, process) in self.system.processes() {
+            let mut depth = 0u32;
+            let mut cursor = process.parent();
+            while let Some(parent_pid) = cursor {
+                depth += 1;
+                if parent_pid == root {
+                    by_depth.push((candidate_pid.as_u32(), depth));
+                    break;
+                }
+                cursor = self
+                    .system
+                    .process(parent_pid)
+                    .and_then(|p| p.parent());
+                if depth > 64 {
+                    break; // guard against any unexpected cycle in the table
+                }
+            }
+        }
+
+        // Leaves first: deepest descendants terminated before their parents.
+        by_depth.sort_by(|a, b| b.1.cmp(&a.1));
+        by_depth.into_iter().map(|(pid, _)| pid).collect()
+    }
+
+    /// Find every running process whose executable/command name contains `name_fragment`.
+    pub fn pids_matching_name(&self, name_fragment: &str) -> Vec<u32> {
+        self.system
+            .processes()
+            .iter()
+            .filter(|(_, process)| {
+                process.name().to_string_lossy().contains(name_fragment)
+                    || process
+                        .exe()
+                        .map(|p| p.to_string_lossy().contains(name_fragment))
+                        .unwrap_or(false)
+            })
+            .map(|(pid, _)| pid.as_u32())
+            .collect()
+    }
+}
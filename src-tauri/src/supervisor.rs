@@ -0,0 +1,83 @@
+//! Crash detection and auto-restart for the sidecar process.
+//!
+//! Before this, a sidecar crash after startup left the app silently dead with
+//! no recovery path. `RestartSupervisor` tracks how many times the sidecar
+//! has died back-to-back and which "generation" of restart attempt is
+//! current, so a delayed termination event from an instance we've already
+//! superseded can't spawn a second, redundant restart loop.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+/// Exponential backoff parameters for sidecar restarts.
+pub struct BackoffPolicy {
+    /// Delay before the first restart attempt.
+    pub initial: Duration,
+    /// Upper bound the delay doubles towards.
+    pub max: Duration,
+    /// How long the sidecar must stay up before the failure streak resets.
+    pub reset_after: Duration,
+    /// Give up after this many consecutive failed restarts.
+    pub max_attempts: u32,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(30),
+            reset_after: Duration::from_secs(60),
+            max_attempts: 8,
+        }
+    }
+}
+
+/// Delay to wait before restart attempt number `attempt` (1-based), doubling
+/// from `policy.initial` up to `policy.max`.
+pub fn backoff_delay(attempt: u32, policy: &BackoffPolicy) -> Duration {
+    let shift = attempt.saturating_sub(1).min(16);
+    let millis = policy
+        .initial
+        .as_millis()
+        .saturating_mul(1u128 << shift)
+        .min(policy.max.as_millis());
+    Duration::from_millis(millis as u64)
+}
+
+/// Tracks restart generations and the consecutive-crash count across the
+/// lifetime of the app.
+pub struct RestartSupervisor {
+    generation: AtomicU32,
+    consecutive_failures: AtomicU32,
+}
+
+impl RestartSupervisor {
+    pub const fn new() -> Self {
+        Self {
+            generation: AtomicU32::new(0),
+            consecutive_failures: AtomicU32::new(0),
+        }
+    }
+
+    /// Start a new restart cycle, invalidating any restart loop still running
+    /// for a previous generation. Returns the new generation id.
+    pub fn begin_restart_cycle(&self) -> u32 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Whether `generation` is still the active one (i.e. no newer crash has
+    /// superseded it).
+    pub fn is_current(&self, generation: u32) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+
+    /// Record a crash/failed restart attempt and return the new consecutive count.
+    pub fn record_failure(&self) -> u32 {
+        self.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Reset the consecutive-failure streak once the sidecar has proven stable.
+    pub fn reset_failures(&self) {
+        self.consecutive_failures.store(0, Ordering::SeqCst);
+    }
+}